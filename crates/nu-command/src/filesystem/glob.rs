@@ -1,5 +1,7 @@
+use std::path::{Path, PathBuf};
+
 use nu_engine::command_prelude::*;
-use nu_protocol::{FromValue, ListStream};
+use nu_protocol::{FromValue, ListStream, Record, Spanned};
 
 use nu_glob2::{Glob as NuGlob, WalkOptions};
 
@@ -13,8 +15,26 @@ impl Command for Glob {
 
     fn signature(&self) -> Signature {
         Signature::build("glob")
-            .input_output_types(vec![(Type::Nothing, Type::List(Box::new(Type::String)))])
-            .required("glob", SyntaxShape::OneOf(vec![SyntaxShape::String, SyntaxShape::GlobPattern]), "The glob expression.")
+            .input_output_types(vec![
+                (Type::Nothing, Type::List(Box::new(Type::String))),
+                (Type::Nothing, Type::table()),
+                (
+                    Type::List(Box::new(Type::String)),
+                    Type::List(Box::new(Type::String)),
+                ),
+            ])
+            .required(
+                "glob",
+                SyntaxShape::OneOf(vec![
+                    SyntaxShape::String,
+                    SyntaxShape::GlobPattern,
+                    SyntaxShape::List(Box::new(SyntaxShape::OneOf(vec![
+                        SyntaxShape::String,
+                        SyntaxShape::GlobPattern,
+                    ]))),
+                ]),
+                "The glob expression. A list of expressions is matched in a single filesystem traversal. A `re:` prefix matches the whole path with a regular expression instead of wax glob syntax.",
+            )
             .named(
                 "depth",
                 SyntaxShape::Int,
@@ -47,6 +67,17 @@ impl Command for Glob {
                 "Patterns to exclude from the search: `glob` will not walk the inside of directories matching the excluded patterns.",
                 Some('e'),
             )
+            .named(
+                "exclude-file",
+                SyntaxShape::Filepath,
+                "Read exclude patterns from a file, one per line, in the style of .gitignore. Lines may be prefixed with `glob:` (the default), `path:` for a literal rooted prefix, or `re:` for a regular expression; a leading `!` re-includes a path an earlier pattern excluded.",
+                None,
+            )
+            .switch(
+                "capture",
+                "Return a table with a `path` column plus one column per named or positional `(?<name>...)`/`(...)` capture in the pattern, instead of a list of paths.",
+                Some('c'),
+            )
             .category(Category::FileSystem)
     }
 
@@ -120,6 +151,31 @@ impl Command for Glob {
                 example: r#"glob "**/*.txt" --follow-symlinks"#,
                 result: None,
             },
+            Example {
+                description: "Search for files, excluding anything matched by patterns listed in .gitignore",
+                example: r#"glob **/* --exclude-file .gitignore"#,
+                result: None,
+            },
+            Example {
+                description: "Filter a list of paths against a glob pattern without touching the filesystem",
+                example: r#"["src/main.rs" "README.md"] | glob **/*.rs"#,
+                result: Some(Value::test_list(vec![Value::test_string("src/main.rs")])),
+            },
+            Example {
+                description: "Search for several extensions in a single traversal",
+                example: "glob [**/*.rs **/*.toml **/*.md]",
+                result: None,
+            },
+            Example {
+                description: "Parse matched paths into a table using named captures",
+                example: r#"glob '**/(?<name>*).(?<ext>{rs,toml})' --capture"#,
+                result: None,
+            },
+            Example {
+                description: "Match using a raw regular expression instead of wax glob syntax",
+                example: r#"glob 're:^src/.*\.rs$'"#,
+                result: None,
+            },
         ]
     }
 
@@ -132,9 +188,15 @@ impl Command for Glob {
         engine_state: &EngineState,
         stack: &mut Stack,
         call: &Call,
-        _input: PipelineData,
+        input: PipelineData,
     ) -> Result<PipelineData, ShellError> {
-        new_glob(engine_state, stack, call)
+        match input {
+            PipelineData::Empty => new_glob(engine_state, stack, call),
+            PipelineData::Value(Value::Nothing { .. }, _) => {
+                new_glob(engine_state, stack, call)
+            }
+            input => filter_input_paths(engine_state, stack, call, input),
+        }
     }
 }
 
@@ -164,17 +226,32 @@ fn build_walk_options(
         None => Vec::new(),
         Some(list) => compile_exclusions(list)?,
     };
-    eprintln!("list = {:#?}", exclusion_patterns);
 
-    let options = WalkOptions::build()
+    let mut options = WalkOptions::build()
         .max_depth(call.get_flag(engine_state, stack, "depth")?)
         .exclude_files(call.has_flag(engine_state, stack, "no-file")?)
         .exclude_directories(call.has_flag(engine_state, stack, "no-dir")?)
         .exclude_symlinks(call.has_flag(engine_state, stack, "no-symlink")?)
         .exclude_patterns(exclusion_patterns);
+
+    if let Some(exclude_file) = call.get_flag::<Spanned<PathBuf>>(engine_state, stack, "exclude-file")? {
+        options = options
+            .exclude_from_file(&exclude_file.item)
+            .map_err(|err| err.into_shell_error(exclude_file.span))?;
+    }
+
     Ok(options)
 }
 
+/// Parse the required `glob` argument into one or more [`NuGlob`]s: either a
+/// single pattern, or a list of patterns to match in one traversal.
+fn globs_from_value(value: Value) -> Result<Vec<NuGlob>, ShellError> {
+    match value {
+        Value::List { vals, .. } => vals.into_iter().map(NuGlob::from_value).collect(),
+        scalar => NuGlob::from_value(scalar).map(|glob| vec![glob]),
+    }
+}
+
 fn new_glob(
     engine_state: &EngineState,
     stack: &mut Stack,
@@ -184,12 +261,17 @@ fn new_glob(
     let input_value: Value = call.req(engine_state, stack, 0)?;
 
     let options = build_walk_options(engine_state, stack, call)?;
-    let glob = NuGlob::from_value(input_value)?
-        .compile(options)
+    let globs = globs_from_value(input_value)?;
+
+    if call.has_flag(engine_state, stack, "capture")? {
+        return new_glob_with_captures(globs, options, span, engine_state);
+    }
+
+    let glob_set = nu_glob2::CompiledGlobSet::compile(globs, options)
         .map_err(|e| e.into_shell_error(span))?;
 
     Ok(PipelineData::from(ListStream::new(
-        glob.walk().map(move |result| match result {
+        glob_set.walk().map(move |result| match result {
             Ok(path) => Value::string(path.to_string_lossy(), span),
             Err(err) => Value::error(err.into_shell_error(span), span),
         }),
@@ -198,6 +280,102 @@ fn new_glob(
     )))
 }
 
+/// Walk each glob separately, yielding one record per match: a `path` column
+/// plus one column per named or positional capture its pattern recorded.
+fn new_glob_with_captures(
+    globs: Vec<NuGlob>,
+    options: WalkOptions,
+    span: Span,
+    engine_state: &EngineState,
+) -> Result<PipelineData, ShellError> {
+    let compiled = globs
+        .into_iter()
+        .map(|glob| glob.compile(options.clone()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.into_shell_error(span))?;
+
+    let records = compiled
+        .into_iter()
+        .flat_map(nu_glob2::CompiledGlob::walk_with_captures)
+        .map(move |result| match result {
+            Ok((path, captures)) => capture_record(path, captures, span),
+            Err(err) => Value::error(err.into_shell_error(span), span),
+        });
+
+    Ok(PipelineData::from(ListStream::new(
+        records,
+        span,
+        engine_state.signals().clone(),
+    )))
+}
+
+/// Build the `{path: ..., <capture columns>: ...}` record for one match.
+/// Unnamed captures are numbered in pattern order: `capture1`, `capture2`, ...
+/// A capture literally named `path` would otherwise collide with the
+/// mandatory `path` column, so it's renamed to `path_capture`.
+fn capture_record(path: PathBuf, captures: Vec<nu_glob2::PatternCapture>, span: Span) -> Value {
+    let mut record = Record::new();
+    record.push("path", Value::string(path.to_string_lossy(), span));
+
+    let mut positional = 0;
+    for capture in captures {
+        let column = match capture.name {
+            Some(name) if name == "path" => "path_capture".to_string(),
+            Some(name) => name,
+            None => {
+                positional += 1;
+                format!("capture{positional}")
+            }
+        };
+        record.push(column, Value::string(capture.text, span));
+    }
+
+    Value::record(record, span)
+}
+
+/// When `glob` is given a list of path strings on its input, it filters them
+/// through the pattern instead of walking the filesystem - no filesystem
+/// access happens in this mode. An element that isn't a string becomes an
+/// error in the output instead of being silently dropped.
+fn filter_input_paths(
+    engine_state: &EngineState,
+    stack: &mut Stack,
+    call: &Call,
+    input: PipelineData,
+) -> Result<PipelineData, ShellError> {
+    let span = call.head;
+    let input_value: Value = call.req(engine_state, stack, 0)?;
+
+    let globs = globs_from_value(input_value)?
+        .into_iter()
+        .map(|glob| glob.compile(WalkOptions::default()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.into_shell_error(span))?;
+
+    Ok(PipelineData::from(ListStream::new(
+        input.into_iter().filter_map(move |value| {
+            let value_span = value.span();
+            match value.as_str() {
+                Ok(path) => globs
+                    .iter()
+                    .any(|glob| glob.matches(Path::new(path)))
+                    .then_some(value),
+                Err(_) => Some(Value::error(
+                    ShellError::OnlySupportsThisInputType {
+                        exp_input_type: "string".into(),
+                        wrong_type: value.get_type().to_string(),
+                        dst_span: span,
+                        src_span: value_span,
+                    },
+                    value_span,
+                )),
+            }
+        }),
+        span,
+        engine_state.signals().clone(),
+    )))
+}
+
 #[cfg(windows)]
 #[cfg(test)]
 mod windows_tests {
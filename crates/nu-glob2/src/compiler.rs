@@ -0,0 +1,278 @@
+//! Turns a parsed [`crate::parser::Pattern`] (or a `path:`/`re:` pattern
+//! string) into a [`Program`] that `matcher` can run against candidate
+//! paths.
+//!
+//! `compile_path_prefix` backs the `path:` --exclude-file selector;
+//! `compile_regex` backs both the `re:` selector and standalone `re:` glob
+//! patterns, which share the same [`ProgramKind::Regex`] plumbing.
+
+use std::fmt;
+use std::path::{Component as PathComponent, PathBuf};
+
+use regex::Regex;
+
+use crate::error::GlobError;
+use crate::parser::{Pattern, Token};
+use crate::GlobResult;
+
+/// One compiled path component.
+#[derive(Debug, Clone)]
+pub(crate) enum Component {
+    /// A component made entirely of literal text (no wildcards).
+    Literal(String),
+    /// `**` - matches zero or more path components.
+    Recursive,
+    /// A component containing at least one wildcard/class/alternative token.
+    Pattern(Vec<Token>),
+}
+
+/// The matching strategy a [`Program`] was compiled for.
+#[derive(Debug, Clone)]
+pub(crate) enum ProgramKind {
+    /// A wax-style glob, matched component by component.
+    Components(Vec<Component>),
+    /// A literal, rooted directory/file prefix (`path:`), matched as its
+    /// own literal components followed by an implicit `**`.
+    PathPrefix(Vec<Component>),
+    /// A raw regular expression (`re:`), matched against the whole path.
+    Regex(Regex),
+}
+
+/// A compiled glob pattern, ready to be matched against paths.
+#[derive(Debug, Clone)]
+pub struct Program {
+    source: String,
+    pub(crate) case_insensitive: bool,
+    pub(crate) kind: ProgramKind,
+    /// The root a relative walk should start from; `None` for patterns that
+    /// don't begin with a root separator, in which case the current
+    /// directory is used instead.
+    pub(crate) absolute_prefix: Option<PathBuf>,
+    /// The longest leading run of literal path components, i.e. everything
+    /// before the first wildcard/alternation/recursive component. A walk
+    /// can never find a match outside of `absolute_prefix`/cwd joined with
+    /// this, so it's used to narrow down where a traversal needs to start.
+    pub(crate) invariant_prefix: Vec<String>,
+}
+
+impl Program {
+    /// Check whether `path` is a complete match for this program.
+    pub fn matches(&self, path: &std::path::Path) -> bool {
+        crate::matcher::path_matches(path, self).valid_as_complete_match
+    }
+
+    /// Check whether `path` (typically a directory) could still lead to a
+    /// complete match somewhere below it, and is therefore worth recursing
+    /// into during a walk.
+    pub(crate) fn could_lead_to_match(&self, path: &std::path::Path) -> bool {
+        crate::matcher::could_lead_to_match(path, self)
+    }
+
+    /// The [`Program::invariant_prefix`] joined into a single relative path.
+    pub(crate) fn invariant_prefix_path(&self) -> PathBuf {
+        self.invariant_prefix.iter().collect()
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Compile a parsed wax [`Pattern`] into a [`Program`].
+pub(crate) fn compile(source: &str, pattern: &Pattern) -> GlobResult<Program> {
+    let (components, absolute_prefix) = compile_components(&pattern.components);
+    // Under `(?i)` a literal component also matches differently-cased
+    // directories, so it can't narrow where the walk starts.
+    let invariant_prefix = if pattern.case_insensitive {
+        Vec::new()
+    } else {
+        invariant_prefix(&components)
+    };
+
+    Ok(Program {
+        source: source.to_string(),
+        case_insensitive: pattern.case_insensitive,
+        kind: ProgramKind::Components(components),
+        absolute_prefix,
+        invariant_prefix,
+    })
+}
+
+/// Compile a `path:` selector into a [`Program`] that matches the literal
+/// path itself and everything beneath it.
+pub(crate) fn compile_path_prefix(source: &str) -> GlobResult<Program> {
+    let literal_components: Vec<Vec<Token>> = std::path::Path::new(source)
+        .components()
+        .filter_map(|component| match component {
+            PathComponent::Normal(part) => {
+                Some(vec![Token::Literal(part.to_string_lossy().into_owned())])
+            }
+            _ => None,
+        })
+        .collect();
+
+    let (mut components, absolute_prefix) = compile_components(&literal_components);
+    let invariant_prefix = invariant_prefix(&components);
+    components.push(Component::Recursive);
+
+    Ok(Program {
+        source: source.to_string(),
+        case_insensitive: false,
+        kind: ProgramKind::PathPrefix(components),
+        absolute_prefix,
+        invariant_prefix,
+    })
+}
+
+/// Compile a `re:` selector into a [`Program`] that matches the whole path
+/// string against a regular expression.
+pub(crate) fn compile_regex(source: &str) -> GlobResult<Program> {
+    let regex = Regex::new(source).map_err(|_| GlobError::UnparseableInput {
+        input: source.to_string(),
+    })?;
+
+    let (absolute_prefix, invariant_prefix) = anchored_literal_prefix(source);
+
+    Ok(Program {
+        source: source.to_string(),
+        case_insensitive: false,
+        kind: ProgramKind::Regex(regex),
+        absolute_prefix,
+        invariant_prefix,
+    })
+}
+
+/// If `source` is anchored with a leading `^` followed by a run of literal,
+/// regex-metacharacter-free path components, split that run into components
+/// (and, if it begins with `/`, note the filesystem root to anchor at) so a
+/// walk can start from that subtree instead of the whole filesystem. A regex
+/// with no such anchor gets no prefix, the same as an unrestricted wax glob.
+fn anchored_literal_prefix(source: &str) -> (Option<PathBuf>, Vec<String>) {
+    let Some(rest) = source.strip_prefix('^') else {
+        return (None, Vec::new());
+    };
+
+    // With a `|`, a branch need not start with the literal run at all
+    // (`^src/a|^lib/b`), so no prefix is assumed.
+    if rest.contains('|') {
+        return (None, Vec::new());
+    }
+
+    // `.` is excluded even though it's often written literally in a path:
+    // unescaped, it's the regex any-char metacharacter, so treating it as
+    // literal here would prune out real matches (e.g. `^v1.0/` would wrongly
+    // narrow the walk to exactly `v1.0/`, skipping a match like `v1X0/`).
+    let mut literal_end = rest
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/')))
+        .unwrap_or(rest.len());
+    // A quantifier right after the run makes its last character optional or
+    // repeatable (`^src/?x` also matches `srcx`), so that character is dropped.
+    if rest[literal_end..].starts_with(['?', '*', '+', '{']) {
+        literal_end = rest[..literal_end]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i);
+    }
+
+    // Only keep the run up to the last fully-written path separator: a
+    // partial trailing component (e.g. "src/ma" before a metacharacter)
+    // isn't a real invariant path component.
+    let boundary = rest[..literal_end].rfind('/').map_or(0, |i| i + 1);
+    let literal = &rest[..boundary];
+
+    let absolute_prefix = literal
+        .starts_with('/')
+        .then(|| PathBuf::from(std::path::MAIN_SEPARATOR.to_string()));
+    let invariant_prefix = literal
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(String::from)
+        .collect();
+
+    (absolute_prefix, invariant_prefix)
+}
+
+fn compile_components(tokens: &[Vec<Token>]) -> (Vec<Component>, Option<PathBuf>) {
+    let mut components: Vec<Component> = tokens
+        .iter()
+        .map(|tokens| match tokens.as_slice() {
+            [] => Component::Literal(String::new()),
+            [Token::Wildcard, Token::Wildcard] => Component::Recursive,
+            [Token::Literal(text)] => Component::Literal(text.clone()),
+            other => Component::Pattern(other.to_vec()),
+        })
+        .collect();
+
+    // A pattern that began with `/` parses to a leading empty literal
+    // component; strip it and anchor the walk at the filesystem root.
+    let absolute_prefix = match components.first() {
+        Some(Component::Literal(text)) if text.is_empty() => {
+            components.remove(0);
+            Some(PathBuf::from(std::path::MAIN_SEPARATOR.to_string()))
+        }
+        _ => None,
+    };
+
+    (components, absolute_prefix)
+}
+
+/// The longest leading run of `Component::Literal`s in `components`, i.e.
+/// everything before the first wildcard/alternation/recursive token.
+fn invariant_prefix(components: &[Component]) -> Vec<String> {
+    components
+        .iter()
+        .take_while(|component| matches!(component, Component::Literal(_)))
+        .map(|component| match component {
+            Component::Literal(text) => text.clone(),
+            Component::Recursive | Component::Pattern(_) => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_component_run_is_kept_as_the_invariant_prefix() {
+        let (absolute_prefix, invariant_prefix) = anchored_literal_prefix("^src/main.rs");
+        assert_eq!(absolute_prefix, None);
+        assert_eq!(invariant_prefix, vec!["src".to_string()]);
+    }
+
+    #[test]
+    fn a_rooted_pattern_notes_the_filesystem_root() {
+        let (absolute_prefix, invariant_prefix) = anchored_literal_prefix("^/etc/passwd$");
+        assert_eq!(
+            absolute_prefix,
+            Some(PathBuf::from(std::path::MAIN_SEPARATOR.to_string()))
+        );
+        assert_eq!(invariant_prefix, vec!["etc".to_string()]);
+    }
+
+    #[test]
+    fn an_unanchored_regex_gets_no_prefix() {
+        assert_eq!(
+            anchored_literal_prefix("src/.*\\.rs"),
+            (None, Vec::new())
+        );
+    }
+
+    #[test]
+    fn a_dot_ends_the_literal_run_instead_of_being_treated_as_literal() {
+        // `.` is the regex any-char metacharacter: `v1.0` also matches
+        // `v1X0`, so it must not be folded into the invariant prefix a walk
+        // uses to narrow its starting directory - doing so would prune out
+        // real matches like `v1X0/src/...`.
+        let (_, invariant_prefix) = anchored_literal_prefix("^v1.0/src/foo");
+        assert!(invariant_prefix.is_empty());
+    }
+
+    #[test]
+    fn a_dot_after_a_complete_component_still_keeps_that_component() {
+        let (_, invariant_prefix) = anchored_literal_prefix("^src/v1.0");
+        assert_eq!(invariant_prefix, vec!["src".to_string()]);
+    }
+}
@@ -16,6 +16,9 @@ pub enum GlobError {
         source: std::io::Error,
         path: std::path::PathBuf,
     },
+    #[error("cannot match an absolute and a relative pattern in the same glob list")]
+    #[diagnostic(code(nu_glob2::lib::mixed_absolute_and_relative_patterns))]
+    MixedAbsoluteAndRelativePatterns,
 }
 
 impl GlobError {
@@ -32,6 +35,11 @@ impl GlobError {
             GlobError::Io { source, path } => {
                 nu_protocol::shell_error::io::IoError::new(source, span, path).into()
             }
+            GlobError::MixedAbsoluteAndRelativePatterns => ShellError::InvalidGlobPattern {
+                msg: "cannot match an absolute and a relative pattern in the same glob list"
+                    .to_string(),
+                span,
+            },
         }
     }
 }
@@ -0,0 +1,473 @@
+//! Walks the filesystem, yielding paths that match a compiled program.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::compiler::Program;
+use crate::error::GlobError;
+use crate::matcher::Capture;
+use crate::parser::ExcludeSyntax;
+use crate::{CompiledGlob, Glob, GlobResult};
+
+/// One compiled entry from `--exclude` or `--exclude-file`. Entries are kept
+/// in the order they were written; during traversal they're evaluated in
+/// that order so that a `!`-negated entry can re-admit a path an earlier
+/// entry excluded, the same way `.gitignore` resolves overlapping rules.
+#[derive(Debug, Clone)]
+pub struct ExcludeEntry {
+    pattern: CompiledGlob,
+    negated: bool,
+}
+
+impl ExcludeEntry {
+    fn new(pattern: CompiledGlob, negated: bool) -> Self {
+        ExcludeEntry { pattern, negated }
+    }
+}
+
+/// Options controlling a filesystem walk: depth, which entry types to keep,
+/// and which paths to exclude.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    max_depth: Option<i64>,
+    exclude_files: bool,
+    exclude_directories: bool,
+    exclude_symlinks: bool,
+    exclude_patterns: Vec<ExcludeEntry>,
+}
+
+impl WalkOptions {
+    /// Start building a [`WalkOptions`] from the defaults (no limits, no
+    /// exclusions, every entry type kept).
+    pub fn build() -> Self {
+        Self::default()
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<i64>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn exclude_files(mut self, exclude: bool) -> Self {
+        self.exclude_files = exclude;
+        self
+    }
+
+    pub fn exclude_directories(mut self, exclude: bool) -> Self {
+        self.exclude_directories = exclude;
+        self
+    }
+
+    pub fn exclude_symlinks(mut self, exclude: bool) -> Self {
+        self.exclude_symlinks = exclude;
+        self
+    }
+
+    /// Add already-compiled exclude patterns (e.g. from `--exclude`) to the
+    /// end of the ordered exclude list.
+    pub fn exclude_patterns(mut self, patterns: Vec<CompiledGlob>) -> Self {
+        self.exclude_patterns
+            .extend(patterns.into_iter().map(|pattern| ExcludeEntry::new(pattern, false)));
+        self
+    }
+
+    /// Read an ignore-file (in the style of `.gitignore`/`.hgignore`) and
+    /// append its patterns to the end of the ordered exclude list.
+    ///
+    /// Each non-blank, non-comment line may start with a syntax selector:
+    /// `glob:` (the default) for a wax glob, `path:` for a literal rooted
+    /// prefix, or `re:` for a raw regular expression. A leading `!` marks
+    /// the line as a re-include, overriding any earlier exclude that also
+    /// matched the path.
+    pub fn exclude_from_file(mut self, path: impl AsRef<Path>) -> GlobResult<Self> {
+        let lines = crate::parser::parse_exclude_file(path.as_ref())?;
+        for line in lines {
+            let glob = match line.syntax {
+                ExcludeSyntax::Glob => Glob::new(line.pattern),
+                ExcludeSyntax::Path => Glob::new_path_prefix(line.pattern),
+                ExcludeSyntax::Regex => Glob::new_regex(line.pattern),
+            };
+            let compiled = glob.compile(WalkOptions::default())?;
+            self.exclude_patterns
+                .push(ExcludeEntry::new(compiled, line.negated));
+        }
+        Ok(self)
+    }
+
+    fn is_excluded(&self, path: &Path) -> bool {
+        let mut excluded = false;
+        for entry in &self.exclude_patterns {
+            if entry.pattern.matches(path) {
+                excluded = !entry.negated;
+            }
+        }
+        excluded
+    }
+
+    fn keep_entry(&self, path: &Path) -> bool {
+        if self.is_excluded(path) {
+            return false;
+        }
+        if self.exclude_symlinks && path.is_symlink() {
+            return false;
+        }
+        if self.exclude_files && path.is_file() {
+            return false;
+        }
+        if self.exclude_directories && path.is_dir() {
+            return false;
+        }
+        true
+    }
+}
+
+/// Walk `start` once, yielding every path under it that matches at least one
+/// program in `programs` and passes `options`'s filters. A path that
+/// matches several programs is still only visited - and yielded - once.
+///
+/// `match_root` is the directory each program's components are relative to
+/// (its own `absolute_prefix`, or the current directory); `start` is the
+/// (possibly deeper) directory the walk actually begins reading from, after
+/// narrowing by the programs' invariant literal prefixes. Every visited path
+/// is relativized against `match_root` before being tested, since a
+/// program's components don't include `match_root` itself.
+pub(crate) fn glob(
+    match_root: PathBuf,
+    start: PathBuf,
+    programs: Vec<Arc<Program>>,
+    options: WalkOptions,
+) -> impl Iterator<Item = Result<PathBuf, GlobError>> + Send {
+    glob_with_captures(match_root, start, programs, options)
+        .map(|result| result.map(|(path, _captures)| path))
+}
+
+/// Like [`glob`], but also yields the captures each match produced from any
+/// `(?<name>...)`/`(...)` tokens in the program that matched it - used by
+/// `glob --capture`.
+pub(crate) fn glob_with_captures(
+    match_root: PathBuf,
+    start: PathBuf,
+    programs: Vec<Arc<Program>>,
+    options: WalkOptions,
+) -> impl Iterator<Item = Result<(PathBuf, Vec<Capture>), GlobError>> + Send {
+    Walk {
+        programs,
+        options,
+        match_root,
+        // `start` is only reached via `read_dir` on its parent for every
+        // other directory in the walk, which is what tests it against the
+        // programs; since `start` itself has no such parent, it's marked so
+        // `next` tests it directly instead of assuming it was already ruled
+        // on. This also covers a wildcard-free, fully-literal program (e.g.
+        // `glob Cargo.toml`) whose narrowed `start` *is* the match itself
+        // and may not even be a directory.
+        pending: vec![(start, 0, true)],
+        current: Vec::new(),
+    }
+}
+
+/// A depth-first, stack-based directory walk. Implemented by hand (rather
+/// than a closure/generator) so it can be returned as a plain `Iterator`.
+struct Walk {
+    programs: Vec<Arc<Program>>,
+    options: WalkOptions,
+    match_root: PathBuf,
+    pending: Vec<(PathBuf, i64, bool)>,
+    current: Vec<Result<(PathBuf, Vec<Capture>), GlobError>>,
+}
+
+impl Walk {
+    /// Test `path` (relativized against `match_root`) against every program,
+    /// queuing it as a match if one of them accepts it in full.
+    fn test(&mut self, path: &Path, relative: &Path) {
+        if self.options.keep_entry(relative) {
+            if let Some(captures) = self.programs.iter().find_map(|program| {
+                let result = crate::matcher::path_matches(relative, program);
+                result.valid_as_complete_match.then_some(result.captures)
+            }) {
+                self.current.push(Ok((path.to_path_buf(), captures)));
+            }
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<(PathBuf, Vec<Capture>), GlobError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.current.pop() {
+                return Some(item);
+            }
+
+            let (dir, depth, is_start) = self.pending.pop()?;
+
+            if is_start {
+                let relative = dir.strip_prefix(&self.match_root).unwrap_or(&dir).to_path_buf();
+                self.test(&dir, &relative);
+            }
+
+            // A narrowed `start` can itself be the literal path a
+            // wildcard-free program matches (a file, or a directory whose
+            // entries a less-specific program still needs to see); either
+            // way, `read_dir`-ing something that isn't a directory is
+            // expected to fail, not an error - there's just nothing under it
+            // to descend into.
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(source) => return Some(Err(GlobError::Io { source, path: dir })),
+            };
+
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(source) => {
+                        self.current.push(Err(GlobError::Io { source, path: dir.clone() }));
+                        continue;
+                    }
+                };
+                let path = entry.path();
+                let relative = path.strip_prefix(&self.match_root).unwrap_or(&path);
+
+                // Match-while-traversing: a directory that's excluded, or
+                // that none of the programs could possibly match anything
+                // under, is pruned instead of being fully enumerated and
+                // filtered out afterwards.
+                if path.is_dir()
+                    && self
+                        .options
+                        .max_depth
+                        .is_none_or(|max_depth| depth < max_depth)
+                    && !self.options.is_excluded(relative)
+                    && self
+                        .programs
+                        .iter()
+                        .any(|program| program.could_lead_to_match(relative))
+                {
+                    self.pending.push((path.clone(), depth + 1, false));
+                }
+
+                self.test(&path, relative);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::CompiledGlobSet;
+
+    /// A scratch directory under the OS temp dir, removed when dropped.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "nu_glob2_globber_test_{name}_{}",
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TestDir(dir)
+        }
+
+        fn file(&self, relative: &str) -> &Self {
+            let path = self.0.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+            self
+        }
+
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn walk_names(dir: &TestDir, glob: Glob) -> BTreeSet<String> {
+        let compiled = glob.compile(WalkOptions::default()).unwrap();
+        compiled
+            .walk()
+            .map(|result| {
+                result
+                    .unwrap()
+                    .strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace(std::path::MAIN_SEPARATOR, "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn invariant_prefix_narrows_the_walk_to_the_matching_subtree() {
+        let dir = TestDir::new("prefix_narrowing");
+        dir.file("sub/a.txt").file("other/b.txt");
+
+        let pattern = format!("{}/sub/*.txt", dir.path().display());
+        assert_eq!(
+            walk_names(&dir, Glob::new(pattern)),
+            BTreeSet::from(["sub/a.txt".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_wildcard_free_directory_pattern_is_itself_yielded() {
+        let dir = TestDir::new("wildcard_free_dir");
+        dir.file("sub/a.txt");
+
+        let pattern = format!("{}/sub", dir.path().display());
+        assert_eq!(
+            walk_names(&dir, Glob::new(pattern)),
+            BTreeSet::from(["sub".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_wildcard_free_file_pattern_is_itself_yielded() {
+        let dir = TestDir::new("wildcard_free_file");
+        dir.file("Cargo.toml");
+
+        let pattern = format!("{}/Cargo.toml", dir.path().display());
+        assert_eq!(
+            walk_names(&dir, Glob::new(pattern)),
+            BTreeSet::from(["Cargo.toml".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_directory_that_cannot_lead_to_a_match_is_not_descended_into() {
+        let dir = TestDir::new("subtree_pruning");
+        // A leading `*` keeps the invariant prefix from narrowing the start
+        // path down past `dir`, so only `could_lead_to_match`'s per-component
+        // check - evaluated while the walk is already under way - can rule
+        // `sub2/other` out before it's ever `read_dir`-ed.
+        dir.file("sub1/keep/target.txt").file("sub2/other/target.txt");
+
+        let pattern = format!("{}/*/keep/target.txt", dir.path().display());
+        assert_eq!(
+            walk_names(&dir, Glob::new(pattern)),
+            BTreeSet::from(["sub1/keep/target.txt".to_string()])
+        );
+    }
+
+    #[test]
+    fn exclude_negation_overrides_only_an_earlier_exclude_for_the_same_path() {
+        let dir = TestDir::new("exclude_negation");
+        dir.file("keep.log").file("other.log");
+
+        // The exclude entries are matched against the same relativized path
+        // the walked pattern itself sees (relative to the filesystem root,
+        // since both patterns are absolute here), so they need the same
+        // `dir.path()` prefix as the pattern being walked.
+        let exclude_all_logs = Glob::new(format!("{}/*.log", dir.path().display()))
+            .compile(WalkOptions::default())
+            .unwrap();
+        let reinclude_keep = Glob::new(format!("{}/keep.log", dir.path().display()))
+            .compile(WalkOptions::default())
+            .unwrap();
+
+        let options = WalkOptions::build().exclude_patterns(vec![exclude_all_logs]);
+        // `exclude_patterns` only appends un-negated entries; build the
+        // re-include by hand via the same ordered-list mechanism
+        // `exclude_from_file` uses, so the negation is evaluated after the
+        // earlier exclude, same as a `!keep.log` line appended below
+        // `*.log` in an ignore file.
+        let options = WalkOptions {
+            exclude_patterns: {
+                let mut patterns = options.exclude_patterns.clone();
+                patterns.push(ExcludeEntry::new(reinclude_keep, true));
+                patterns
+            },
+            ..options
+        };
+
+        let pattern = format!("{}/*.log", dir.path().display());
+        let compiled = Glob::new(pattern).compile(options).unwrap();
+        let names: BTreeSet<String> = compiled
+            .walk()
+            .map(|result| {
+                result
+                    .unwrap()
+                    .strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        assert_eq!(names, BTreeSet::from(["keep.log".to_string()]));
+    }
+
+    #[test]
+    fn a_glob_set_visits_a_path_matched_by_several_patterns_only_once() {
+        let dir = TestDir::new("dedup");
+        dir.file("main.rs");
+
+        let base = dir.path().display().to_string();
+        let globs = vec![
+            Glob::new(format!("{base}/*.rs")),
+            Glob::new(format!("{base}/main.*")),
+        ];
+        let set = CompiledGlobSet::compile(globs, WalkOptions::default()).unwrap();
+        let names: Vec<String> = set
+            .walk()
+            .map(|result| {
+                result
+                    .unwrap()
+                    .strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        assert_eq!(names, vec!["main.rs".to_string()]);
+    }
+
+    #[test]
+    fn a_dotted_literal_prefix_does_not_prune_out_a_non_dot_match() {
+        let dir = TestDir::new("dotted_prefix");
+        dir.file("v1.0/src/foo.rs").file("v1X0/src/foo.rs");
+
+        let pattern = format!("re:^{}/v1.0/src/.*", dir.path().display());
+        // `.` in the pattern is the regex any-char, so both `v1.0` and
+        // `v1X0` must be visited and matched - a literal-prefix-narrowed
+        // walk that wrongly treated `.` as literal would only visit `v1.0`.
+        assert_eq!(
+            walk_names(&dir, Glob::new(pattern)),
+            BTreeSet::from(["v1.0/src/foo.rs".to_string(), "v1X0/src/foo.rs".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_list_of_path_strings_is_filtered_by_matching_against_each_glob() {
+        // Mirrors `nu-command`'s `filter_input_paths`: no filesystem walk at
+        // all, just `CompiledGlob::matches` run over a fixed input list with
+        // `.any()` across every pattern in the set.
+        let globs: Vec<CompiledGlob> = vec![
+            Glob::new("*.rs").compile(WalkOptions::default()).unwrap(),
+            Glob::new("*.toml").compile(WalkOptions::default()).unwrap(),
+        ];
+        let inputs = ["main.rs", "Cargo.toml", "README.md", "src/lib.rs"];
+
+        let kept: Vec<&str> = inputs
+            .into_iter()
+            .filter(|path| globs.iter().any(|glob| glob.matches(Path::new(path))))
+            .collect();
+
+        assert_eq!(kept, vec!["main.rs", "Cargo.toml"]);
+    }
+}
@@ -5,6 +5,8 @@ mod parser;
 
 pub mod error;
 
+pub use globber::WalkOptions;
+
 pub(crate) type GlobResult<T> = Result<T, error::GlobError>;
 
 pub enum FilterType {
@@ -13,25 +15,81 @@ pub enum FilterType {
     Symlink,
 }
 
+#[derive(Debug, Clone)]
+enum GlobKind {
+    /// A wax-style glob pattern.
+    Wax(std::sync::Arc<parser::Pattern>),
+    /// A literal, rooted directory/file prefix. Introduced for the `path:`
+    /// exclude-file selector; has no public constructor of its own.
+    PathPrefix,
+    /// A raw regular expression, from [`Glob::new_regex`], a `re:` prefix
+    /// passed to [`Glob::new`], or the `re:` exclude-file selector.
+    Regex,
+}
+
 #[derive(Debug, Clone)]
 pub struct Glob {
     pattern_string: String,
-    pattern: std::sync::Arc<parser::Pattern>,
+    kind: GlobKind,
 }
 
 #[derive(Debug, Clone)]
 pub struct CompiledGlob {
     pattern_string: String,
     program: std::sync::Arc<compiler::Program>,
+    options: WalkOptions,
+}
+
+/// A named or positional capture recorded from a matched path's `(?<name>...)`
+/// / `(...)` tokens, in left-to-right pattern order.
+#[derive(Debug, Clone)]
+pub struct PatternCapture {
+    pub name: Option<String>,
+    pub text: String,
+}
+
+impl From<matcher::Capture> for PatternCapture {
+    fn from(capture: matcher::Capture) -> Self {
+        PatternCapture {
+            name: capture.name,
+            text: capture.text,
+        }
+    }
 }
 
 impl Glob {
-    /// Create a new Glob from a string
+    /// Create a new Glob from a string. A `re:` prefix routes the rest of
+    /// the string through [`Self::new_regex`] instead of wax glob syntax.
     pub fn new(pattern_string: impl Into<String>) -> Self {
         let string = pattern_string.into();
+        match string.strip_prefix("re:") {
+            Some(rest) => Glob::new_regex(rest.to_string()),
+            None => Glob {
+                kind: GlobKind::Wax(std::sync::Arc::new(parser::parse(&string))),
+                pattern_string: string,
+            },
+        }
+    }
+
+    /// Create a Glob that matches a literal, rooted directory/file prefix
+    /// and everything beneath it, as used by the `path:` exclude-file
+    /// selector.
+    pub(crate) fn new_path_prefix(pattern_string: impl Into<String>) -> Self {
         Glob {
-            pattern: std::sync::Arc::new(parser::parse(&string)),
-            pattern_string: string,
+            pattern_string: pattern_string.into(),
+            kind: GlobKind::PathPrefix,
+        }
+    }
+
+    /// Create a Glob that matches a raw regular expression against the
+    /// whole path, instead of wax's component-boundary glob semantics -
+    /// exact control for cases wax's grammar can't express (backreferences,
+    /// precise quantifier placement across separators). Used both directly
+    /// and via a `re:` prefix passed to [`Self::new`].
+    pub fn new_regex(pattern_string: impl Into<String>) -> Self {
+        Glob {
+            pattern_string: pattern_string.into(),
+            kind: GlobKind::Regex,
         }
     }
 
@@ -40,18 +98,33 @@ impl Glob {
         self.pattern_string.as_str()
     }
 
-    /// Return the inner glob Pattern
-    pub fn get_pattern(&self) -> &parser::Pattern {
-        self.pattern.as_ref()
+    /// Return the inner glob Pattern, if this Glob was created from wax
+    /// syntax (`Glob::new`). `path:`/`re:` globs have no wax `Pattern`.
+    pub fn get_pattern(&self) -> Option<&parser::Pattern> {
+        match &self.kind {
+            GlobKind::Wax(pattern) => Some(pattern.as_ref()),
+            GlobKind::PathPrefix | GlobKind::Regex => None,
+        }
     }
 
-    /// Compile the glob to use for matching
-    pub fn compile(self) -> GlobResult<CompiledGlob> {
+    /// Compile the glob to use for matching and walking. `options` controls how a subsequent [`CompiledGlob::walk`]
+    /// traverses the filesystem (depth, entry-type and exclude filters).
+    pub fn compile(self, options: WalkOptions) -> GlobResult<CompiledGlob> {
+        let program = self.compile_program()?;
         Ok(CompiledGlob {
-            pattern_string: self.get_pattern_string().to_string(),
-            program: std::sync::Arc::new(compiler::compile(self.get_pattern())?),
+            pattern_string: self.pattern_string,
+            program: std::sync::Arc::new(program),
+            options,
         })
     }
+
+    fn compile_program(&self) -> GlobResult<compiler::Program> {
+        match &self.kind {
+            GlobKind::Wax(pattern) => compiler::compile(&self.pattern_string, pattern),
+            GlobKind::PathPrefix => compiler::compile_path_prefix(&self.pattern_string),
+            GlobKind::Regex => compiler::compile_regex(&self.pattern_string),
+        }
+    }
 }
 
 impl std::fmt::Display for Glob {
@@ -74,14 +147,23 @@ impl CompiledGlob {
         self.pattern_string.as_str()
     }
 
-    fn absolute_prefix(&self) -> Option<std::path::PathBuf> {
-        self.get_program().absolute_prefix.clone()
+    /// The root the pattern's components are relative to: its own
+    /// `absolute_prefix` if it has one, or the current directory otherwise.
+    fn base_dir(&self) -> std::path::PathBuf {
+        self.get_program()
+            .absolute_prefix
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
     }
 
+    /// The path a walk should start from: [`Self::base_dir`] joined with the
+    /// pattern's invariant literal prefix, since nothing outside of that
+    /// subtree can match. For a wildcard-free pattern this is the literal
+    /// match itself, and may not even be a directory - the walk tests it
+    /// directly rather than assuming it's a readable directory.
     pub fn get_prefix(&self) -> std::path::PathBuf {
-        self.absolute_prefix()
-            .or_else(|| std::env::current_dir().ok())
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
+        self.base_dir().join(self.get_program().invariant_prefix_path())
     }
 
     /// Check if a given path would match the glob pattern
@@ -91,8 +173,28 @@ impl CompiledGlob {
 
     /// Iterate over the filesystem to return paths matching the glob
     pub fn walk(self) -> impl Iterator<Item = Result<std::path::PathBuf, error::GlobError>> + Send {
-        let relative_to = self.get_prefix();
-        globber::glob(relative_to, self.into_program())
+        let match_root = self.base_dir();
+        let start = self.get_prefix();
+        let options = self.options.clone();
+        globber::glob(match_root, start, vec![self.into_program()], options)
+    }
+
+    /// Like [`Self::walk`], but also yields the captures each match produced
+    /// from any `(?<name>...)`/`(...)` tokens in the pattern.
+    pub fn walk_with_captures(
+        self,
+    ) -> impl Iterator<Item = Result<(std::path::PathBuf, Vec<PatternCapture>), error::GlobError>> + Send
+    {
+        let match_root = self.base_dir();
+        let start = self.get_prefix();
+        let options = self.options.clone();
+        globber::glob_with_captures(match_root, start, vec![self.into_program()], options).map(
+            |result| {
+                result.map(|(path, captures)| {
+                    (path, captures.into_iter().map(PatternCapture::from).collect())
+                })
+            },
+        )
     }
 
     pub fn walk_and_filter(
@@ -117,3 +219,118 @@ impl std::fmt::Display for CompiledGlob {
         write!(f, "{}", self.get_pattern_string())
     }
 }
+
+/// A set of compiled glob patterns that share a single filesystem
+/// traversal: a path is yielded as soon as any one pattern matches it,
+/// rather than walking the filesystem once per pattern.
+#[derive(Debug, Clone)]
+pub struct CompiledGlobSet {
+    programs: Vec<std::sync::Arc<compiler::Program>>,
+    options: WalkOptions,
+}
+
+impl CompiledGlobSet {
+    /// Compile `globs` into a set that, when walked, visits each path at
+    /// most once and yields it as soon as any pattern in the set matches.
+    ///
+    /// All patterns must agree on being absolute or relative: mixing them
+    /// would force `match_root`/`get_prefix` to collapse to a shared
+    /// ancestor like `/`, silently breaking the relative patterns' own
+    /// matching (their components aren't relative to `/`).
+    pub fn compile(globs: Vec<Glob>, options: WalkOptions) -> GlobResult<Self> {
+        let programs = globs
+            .iter()
+            .map(|glob| glob.compile_program().map(std::sync::Arc::new))
+            .collect::<GlobResult<Vec<_>>>()?;
+
+        let has_absolute = programs.iter().any(|program| program.absolute_prefix.is_some());
+        let has_relative = programs.iter().any(|program| program.absolute_prefix.is_none());
+        if has_absolute && has_relative {
+            return Err(error::GlobError::MixedAbsoluteAndRelativePatterns);
+        }
+
+        Ok(CompiledGlobSet { programs, options })
+    }
+
+    /// Each pattern's own root: its `absolute_prefix`, or the current
+    /// directory, *without* joining its invariant literal prefix.
+    fn base_dirs(&self) -> Vec<std::path::PathBuf> {
+        self.programs
+            .iter()
+            .map(|program| {
+                program
+                    .absolute_prefix
+                    .clone()
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_else(|| std::path::PathBuf::from("."))
+            })
+            .collect()
+    }
+
+    /// The common ancestor every pattern's components are relative to, used
+    /// to relativize a visited path before testing it against a program.
+    fn match_root(&self) -> std::path::PathBuf {
+        common_ancestor(&self.base_dirs())
+    }
+
+    /// The shallowest path that can contain a match for any pattern in the
+    /// set: the common ancestor of each pattern's own starting path (its
+    /// `absolute_prefix`, or the current directory, joined with its
+    /// invariant literal prefix). As with [`CompiledGlob::get_prefix`], this
+    /// may not be an existing directory - the walk tests it directly.
+    pub fn get_prefix(&self) -> std::path::PathBuf {
+        let starts: Vec<std::path::PathBuf> = self
+            .base_dirs()
+            .into_iter()
+            .zip(&self.programs)
+            .map(|(base, program)| base.join(program.invariant_prefix_path()))
+            .collect();
+        common_ancestor(&starts)
+    }
+
+    /// Iterate over the filesystem, yielding every path that matches at
+    /// least one pattern in the set.
+    pub fn walk(self) -> impl Iterator<Item = Result<std::path::PathBuf, error::GlobError>> + Send {
+        let match_root = self.match_root();
+        let start = self.get_prefix();
+        globber::glob(match_root, start, self.programs, self.options)
+    }
+}
+
+/// The longest path shared as a prefix by every entry in `paths`.
+fn common_ancestor(paths: &[std::path::PathBuf]) -> std::path::PathBuf {
+    let mut iter = paths.iter();
+    let Some(first) = iter.next() else {
+        return std::path::PathBuf::from(".");
+    };
+
+    let mut common: Vec<std::path::Component> = first.components().collect();
+    for path in iter {
+        let components: Vec<std::path::Component> = path.components().collect();
+        let shared = common
+            .iter()
+            .zip(&components)
+            .take_while(|(a, b)| a == b)
+            .count();
+        common.truncate(shared);
+    }
+    common.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_glob_set_rejects_mixing_absolute_and_relative_patterns() {
+        let globs = vec![Glob::new("/etc/x"), Glob::new("*.toml")];
+        let err = CompiledGlobSet::compile(globs, WalkOptions::default()).unwrap_err();
+        assert!(matches!(err, error::GlobError::MixedAbsoluteAndRelativePatterns));
+    }
+
+    #[test]
+    fn a_glob_set_of_only_relative_patterns_still_compiles() {
+        let globs = vec![Glob::new("*.rs"), Glob::new("*.toml")];
+        assert!(CompiledGlobSet::compile(globs, WalkOptions::default()).is_ok());
+    }
+}
@@ -24,16 +24,17 @@ fn run_cmd() -> Result<(), String> {
     let glob = Glob::new(pattern_string.to_string_lossy());
 
     match args.next().map(|s| s.into_encoded_bytes()).as_deref() {
-        Some(b"parse") => {
-            println!("{:#?}", glob.get_pattern());
-        }
+        Some(b"parse") => match glob.get_pattern() {
+            Some(pattern) => println!("{:#?}", pattern),
+            None => println!("(no wax pattern for this glob kind)"),
+        },
         Some(b"compile") => {
-            let compiled_glob = glob.compile().map_err(convert_error)?;
+            let compiled_glob = glob.compile(WalkOptions::default()).map_err(convert_error)?;
             print!("{}", compiled_glob.get_program());
         }
         Some(b"matches") => {
             let path: PathBuf = args.next().ok_or("no path given to match on")?.into();
-            let program = glob.compile().map_err(convert_error)?;
+            let program = glob.compile(WalkOptions::default()).map_err(convert_error)?;
             if program.matches(&path) {
                 println!("{} does match the path \"{}\"", program, path.display());
             } else {
@@ -41,7 +42,7 @@ fn run_cmd() -> Result<(), String> {
             }
         }
         Some(b"glob") => {
-            let program = glob.compile().map_err(convert_error)?;
+            let program = glob.compile(WalkOptions::default()).map_err(convert_error)?;
             let mut stdout = std::io::stdout();
             let mut failed = false;
             for result in program.walk() {
@@ -0,0 +1,322 @@
+//! Matches candidate paths against a compiled [`Program`].
+
+use std::path::{Component as PathComponent, Path};
+
+use crate::compiler::{Component, Program, ProgramKind};
+use crate::parser::{ClassItem, Token};
+
+/// A named or positional capture extracted from a `(?<name>...)`/`(...)`
+/// token, recording the substring its wrapped tokens consumed.
+#[derive(Debug, Clone)]
+pub(crate) struct Capture {
+    pub(crate) name: Option<String>,
+    pub(crate) text: String,
+}
+
+/// The outcome of matching a path against a program.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MatchResult {
+    /// The path, taken as a whole, satisfies the program exactly.
+    pub(crate) valid_as_complete_match: bool,
+    /// Captures collected along the way, in left-to-right pattern order.
+    /// Only populated when `valid_as_complete_match` is `true`.
+    pub(crate) captures: Vec<Capture>,
+}
+
+/// Match `path` against `program`, returning whether it's a complete match.
+pub(crate) fn path_matches(path: &Path, program: &Program) -> MatchResult {
+    let (valid_as_complete_match, captures) = match &program.kind {
+        ProgramKind::Components(components) | ProgramKind::PathPrefix(components) => {
+            let path_components: Vec<String> = path
+                .components()
+                .filter_map(|component| match component {
+                    PathComponent::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect();
+            match match_components(components, &path_components, program.case_insensitive) {
+                Some(captures) => (true, captures),
+                None => (false, Vec::new()),
+            }
+        }
+        ProgramKind::Regex(regex) => {
+            // `path` was relativized against `match_root` before reaching
+            // here, which strips the leading separator a rooted pattern
+            // (`absolute_prefix` is `Some`) was anchored against - put it
+            // back so e.g. `re:^/etc/.*` is tested against `/etc/...`
+            // rather than `etc/...`.
+            let full_path = match &program.absolute_prefix {
+                Some(prefix) => prefix.join(path),
+                None => path.to_path_buf(),
+            };
+            (regex.is_match(&full_path.to_string_lossy()), Vec::new())
+        }
+    };
+
+    MatchResult {
+        valid_as_complete_match,
+        captures,
+    }
+}
+
+/// Check whether `path` - typically a directory reached partway through a
+/// walk - could still be on the way to a complete match, i.e. whether it's
+/// worth recursing into. Unlike [`path_matches`], a path shorter than the
+/// program is fine here: the missing components may come from deeper in
+/// the walk.
+pub(crate) fn could_lead_to_match(path: &Path, program: &Program) -> bool {
+    match &program.kind {
+        ProgramKind::Components(components) | ProgramKind::PathPrefix(components) => {
+            let path_components: Vec<String> = path
+                .components()
+                .filter_map(|component| match component {
+                    PathComponent::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect();
+            prefix_compatible(components, &path_components, program.case_insensitive)
+        }
+        // A raw regex isn't anchored to path components, so there's no
+        // cheap way to tell whether a partial path could still match -
+        // always recurse.
+        ProgramKind::Regex(_) => true,
+    }
+}
+
+fn prefix_compatible(pattern: &[Component], path: &[String], case_insensitive: bool) -> bool {
+    match path.split_first() {
+        None => true,
+        Some((path_head, path_rest)) => match pattern.split_first() {
+            None => false,
+            Some((Component::Recursive, rest)) => {
+                prefix_compatible(pattern, path_rest, case_insensitive)
+                    || prefix_compatible(rest, path, case_insensitive)
+            }
+            Some((head, rest)) => {
+                component_matches(head, path_head, case_insensitive).is_some()
+                    && prefix_compatible(rest, path_rest, case_insensitive)
+            }
+        },
+    }
+}
+
+/// Like [`prefix_compatible`]/[`could_lead_to_match`]'s component check, but
+/// for a complete match: also collects captures, in left-to-right pattern
+/// order, from any `(?<name>...)`/`(...)` tokens along the way.
+fn match_components(
+    pattern: &[Component],
+    path: &[String],
+    case_insensitive: bool,
+) -> Option<Vec<Capture>> {
+    match pattern.split_first() {
+        None => path.is_empty().then(Vec::new),
+        Some((Component::Recursive, rest)) => {
+            // `**` may consume zero or more path components; try every split.
+            (0..=path.len()).find_map(|n| match_components(rest, &path[n..], case_insensitive))
+        }
+        Some((head, rest)) => match path.split_first() {
+            None => None,
+            Some((path_head, path_rest)) => {
+                let head_captures = component_matches(head, path_head, case_insensitive)?;
+                let rest_captures = match_components(rest, path_rest, case_insensitive)?;
+                Some(head_captures.into_iter().chain(rest_captures).collect())
+            }
+        },
+    }
+}
+
+fn component_matches(
+    pattern: &Component,
+    path_component: &str,
+    case_insensitive: bool,
+) -> Option<Vec<Capture>> {
+    match pattern {
+        Component::Recursive => Some(Vec::new()),
+        Component::Literal(text) => text_eq(text, path_component, case_insensitive).then(Vec::new),
+        Component::Pattern(tokens) => match_tokens(tokens, path_component, case_insensitive),
+    }
+}
+
+/// Match a sequence of tokens (everything but `**`, which only appears as a
+/// whole component) against an entire path component string.
+fn match_tokens(tokens: &[Token], text: &str, case_insensitive: bool) -> Option<Vec<Capture>> {
+    let chars: Vec<char> = text.chars().collect();
+    match_tokens_at(tokens, &chars, case_insensitive)
+}
+
+fn match_tokens_at(
+    tokens: &[Token],
+    chars: &[char],
+    case_insensitive: bool,
+) -> Option<Vec<Capture>> {
+    match tokens.split_first() {
+        None => chars.is_empty().then(Vec::new),
+        Some((Token::Literal(text), rest)) => {
+            let text_chars: Vec<char> = text.chars().collect();
+            if chars.len() >= text_chars.len()
+                && chars_eq(&chars[..text_chars.len()], &text_chars, case_insensitive)
+            {
+                match_tokens_at(rest, &chars[text_chars.len()..], case_insensitive)
+            } else {
+                None
+            }
+        }
+        Some((Token::AnyChar, rest)) => {
+            if chars.is_empty() {
+                None
+            } else {
+                match_tokens_at(rest, &chars[1..], case_insensitive)
+            }
+        }
+        Some((Token::Wildcard, rest)) => {
+            (0..=chars.len()).find_map(|n| match_tokens_at(rest, &chars[n..], case_insensitive))
+        }
+        Some((Token::Class { negated, items }, rest)) => {
+            if !chars.is_empty() && (class_contains(items, chars[0], case_insensitive) != *negated)
+            {
+                match_tokens_at(rest, &chars[1..], case_insensitive)
+            } else {
+                None
+            }
+        }
+        Some((Token::Alternative(branches), rest)) => branches.iter().find_map(|branch| {
+            (0..=chars.len()).find_map(|n| {
+                let branch_captures = match_tokens_at(branch, &chars[..n], case_insensitive)?;
+                let rest_captures = match_tokens_at(rest, &chars[n..], case_insensitive)?;
+                Some(branch_captures.into_iter().chain(rest_captures).collect())
+            })
+        }),
+        Some((Token::Capture { name, tokens: inner }, rest)) => (0..=chars.len()).find_map(|n| {
+            let inner_captures = match_tokens_at(inner, &chars[..n], case_insensitive)?;
+            let rest_captures = match_tokens_at(rest, &chars[n..], case_insensitive)?;
+            let captured = Capture {
+                name: name.clone(),
+                text: chars[..n].iter().collect(),
+            };
+            Some(
+                std::iter::once(captured)
+                    .chain(inner_captures)
+                    .chain(rest_captures)
+                    .collect(),
+            )
+        }),
+    }
+}
+
+fn class_contains(items: &[ClassItem], c: char, case_insensitive: bool) -> bool {
+    items.iter().any(|item| match item {
+        ClassItem::Char(item_c) => char_eq(*item_c, c, case_insensitive),
+        ClassItem::Range(start, end) => {
+            if case_insensitive {
+                let c = c.to_ascii_lowercase();
+                (start.to_ascii_lowercase()..=end.to_ascii_lowercase()).contains(&c)
+                    || (*start..=*end).contains(&c)
+            } else {
+                (*start..=*end).contains(&c)
+            }
+        }
+    })
+}
+
+fn char_eq(a: char, b: char, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.eq_ignore_ascii_case(&b)
+    } else {
+        a == b
+    }
+}
+
+fn chars_eq(a: &[char], b: &[char], case_insensitive: bool) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| char_eq(*a, *b, case_insensitive))
+}
+
+fn text_eq(a: &str, b: &str, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn program(source: &str) -> Program {
+        let pattern = crate::parser::parse(source);
+        crate::compiler::compile(source, &pattern).unwrap()
+    }
+
+    fn captures_for(source: &str, path: &str) -> Option<Vec<(Option<String>, String)>> {
+        let result = path_matches(Path::new(path), &program(source));
+        result.valid_as_complete_match.then(|| {
+            result
+                .captures
+                .into_iter()
+                .map(|capture| (capture.name, capture.text))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn unnamed_capture_records_the_matched_substring() {
+        assert_eq!(
+            captures_for("(*).rs", "main.rs"),
+            Some(vec![(None, "main".to_string())])
+        );
+    }
+
+    #[test]
+    fn named_capture_is_recorded_under_its_name() {
+        assert_eq!(
+            captures_for("(?<name>*).(?<ext>{rs,toml})", "main.rs"),
+            Some(vec![
+                (Some("name".to_string()), "main".to_string()),
+                (Some("ext".to_string()), "rs".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn captures_are_recorded_in_left_to_right_pattern_order() {
+        assert_eq!(
+            captures_for("(?<a>?)(?<b>?)(?<c>?)", "xyz"),
+            Some(vec![
+                (Some("a".to_string()), "x".to_string()),
+                (Some("b".to_string()), "y".to_string()),
+                (Some("c".to_string()), "z".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn no_captures_on_a_pattern_with_none() {
+        assert_eq!(captures_for("*.rs", "main.rs"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn no_match_yields_no_captures() {
+        assert_eq!(captures_for("(?<name>*).rs", "main.toml"), None);
+    }
+
+    /// Simulates how `globber::Walk` calls into `path_matches` for a rooted
+    /// `re:` program: it relativizes the visited path against `match_root`
+    /// (which is the filesystem root when `absolute_prefix` is `Some`)
+    /// before testing it, so the leading separator the user's `^/...`
+    /// pattern is anchored on is stripped off by the time it gets here.
+    #[test]
+    fn a_rooted_regex_matches_its_relativized_path() {
+        let program = crate::compiler::compile_regex(r"^/etc/passwd$").unwrap();
+        assert!(program.absolute_prefix.is_some());
+        assert!(path_matches(Path::new("etc/passwd"), &program).valid_as_complete_match);
+    }
+
+    #[test]
+    fn an_unrooted_regex_is_unaffected() {
+        let program = crate::compiler::compile_regex(r"^src/.*\.rs$").unwrap();
+        assert!(program.absolute_prefix.is_none());
+        assert!(path_matches(Path::new("src/main.rs"), &program).valid_as_complete_match);
+    }
+}
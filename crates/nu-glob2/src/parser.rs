@@ -0,0 +1,343 @@
+//! Tokenizes glob pattern strings into a [`Pattern`] that `compiler` can turn
+//! into a matchable [`crate::compiler::Program`].
+//!
+//! This only covers the subset of wax's glob grammar that `nu-glob2` needs:
+//! literals, `?`, `*`, `**`, `[...]` classes, `{...}` alternatives and
+//! `(?<name>...)`/`(...)` captures, plus a leading `(?i)` flag for
+//! case-insensitive matching.
+
+use std::path::Path;
+
+use crate::{GlobResult, error::GlobError};
+
+/// A single token within one path component of a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Token {
+    /// Literal text, matched verbatim (respecting case-sensitivity).
+    Literal(String),
+    /// `?` - matches exactly one character.
+    AnyChar,
+    /// `*` - matches any run of characters within a single path component.
+    Wildcard,
+    /// `[...]` / `[!...]` - matches one character from (or not from) a set.
+    Class { negated: bool, items: Vec<ClassItem> },
+    /// `{a,b,c}` - matches any one of the listed alternative sub-patterns.
+    Alternative(Vec<Vec<Token>>),
+    /// `(?<name>...)` or `(...)` - records the substring consumed by the
+    /// wrapped tokens under `name`, or positionally if unnamed.
+    Capture { name: Option<String>, tokens: Vec<Token> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ClassItem {
+    Char(char),
+    Range(char, char),
+}
+
+/// A parsed, but not yet compiled, glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub(crate) case_insensitive: bool,
+    /// One entry per `/`-separated path component. A component of
+    /// `[Token::Wildcard, Token::Wildcard]` (i.e. a bare `**`) is recognized
+    /// by the compiler as the recursive-descent component.
+    pub(crate) components: Vec<Vec<Token>>,
+}
+
+/// Parse `input` into a [`Pattern`]. This never fails: an unterminated `[`,
+/// `{` or `(` takes the rest of its path component as its contents, and the
+/// compiler accepts every parsed pattern.
+pub(crate) fn parse(input: &str) -> Pattern {
+    let (case_insensitive, rest) = match input.strip_prefix("(?i)") {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+
+    let components = rest
+        .split('/')
+        .map(|component| {
+            if component == "**" {
+                vec![Token::Wildcard, Token::Wildcard]
+            } else {
+                parse_component(component)
+            }
+        })
+        .collect();
+
+    Pattern {
+        case_insensitive,
+        components,
+    }
+}
+
+/// Parse a single `/`-delimited path component (or an `{...}` alternative
+/// branch, which has the same grammar) into a sequence of tokens.
+fn parse_component(component: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = component.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '?' => {
+                flush_literal(&mut literal, &mut tokens);
+                tokens.push(Token::AnyChar);
+            }
+            '*' => {
+                flush_literal(&mut literal, &mut tokens);
+                tokens.push(Token::Wildcard);
+            }
+            '[' => {
+                let rest: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                flush_literal(&mut literal, &mut tokens);
+                tokens.push(parse_class(&rest));
+            }
+            '{' => {
+                let mut depth = 1;
+                let mut inner = String::new();
+                for c in chars.by_ref() {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    inner.push(c);
+                }
+                flush_literal(&mut literal, &mut tokens);
+                let branches = split_top_level_commas(&inner)
+                    .into_iter()
+                    .map(|branch| parse_component(&branch))
+                    .collect();
+                tokens.push(Token::Alternative(branches));
+            }
+            '(' => {
+                let name = parse_capture_name(&mut chars);
+                let mut depth = 1;
+                let mut inner = String::new();
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    inner.push(c);
+                }
+                flush_literal(&mut literal, &mut tokens);
+                tokens.push(Token::Capture {
+                    name,
+                    tokens: parse_component(&inner),
+                });
+            }
+            other => literal.push(other),
+        }
+    }
+    flush_literal(&mut literal, &mut tokens);
+    tokens
+}
+
+/// Consume a `?<name>` capture-name prefix right after an opening `(`, if
+/// present, and return the name. Leaves `chars` untouched for a plain,
+/// positional `(...)` capture.
+fn parse_capture_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut lookahead = chars.clone();
+    if lookahead.next() != Some('?') || lookahead.next() != Some('<') {
+        return None;
+    }
+    chars.next();
+    chars.next();
+
+    let mut name = String::new();
+    for c in chars.by_ref() {
+        if c == '>' {
+            break;
+        }
+        name.push(c);
+    }
+    Some(name)
+}
+
+fn flush_literal(literal: &mut String, tokens: &mut Vec<Token>) {
+    if !literal.is_empty() {
+        tokens.push(Token::Literal(std::mem::take(literal)));
+    }
+}
+
+fn parse_class(spec: &str) -> Token {
+    let (negated, spec) = match spec.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, spec),
+    };
+
+    let mut items = Vec::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(c) = chars.next() {
+        if chars.peek() == Some(&'-') {
+            let mut lookahead = chars.clone();
+            lookahead.next();
+            if let Some(end) = lookahead.next() {
+                chars.next();
+                chars.next();
+                items.push(ClassItem::Range(c, end));
+                continue;
+            }
+        }
+        items.push(ClassItem::Char(c));
+    }
+
+    Token::Class { negated, items }
+}
+
+fn split_top_level_commas(input: &str) -> Vec<String> {
+    let mut branches = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in input.chars() {
+        match c {
+            '{' => {
+                depth += 1;
+                current.push(c);
+            }
+            '}' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' if depth == 0 => {
+                branches.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    branches.push(current);
+    branches
+}
+
+/// The syntax selector a line in an exclude/ignore file was written with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExcludeSyntax {
+    /// `glob:` (the default) - a wax-style glob pattern.
+    Glob,
+    /// `path:` - a literal, rooted directory or file prefix.
+    Path,
+    /// `re:` - a raw regular expression.
+    Regex,
+}
+
+/// One non-blank, non-comment line parsed out of an exclude/ignore file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ExcludeLine {
+    pub(crate) syntax: ExcludeSyntax,
+    /// `true` if the line began with `!`: a later re-include that overrides
+    /// any earlier pattern that excluded the same path.
+    pub(crate) negated: bool,
+    pub(crate) pattern: String,
+}
+
+/// Parse a single line from an exclude/ignore file, in the style of
+/// `.gitignore`/`.hgignore`. Returns `None` for blank lines and comments
+/// (lines beginning with `#`).
+fn parse_exclude_line(line: &str) -> Option<ExcludeLine> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    let (syntax, pattern) = if let Some(rest) = line.strip_prefix("glob:") {
+        (ExcludeSyntax::Glob, rest)
+    } else if let Some(rest) = line.strip_prefix("path:") {
+        (ExcludeSyntax::Path, rest)
+    } else if let Some(rest) = line.strip_prefix("re:") {
+        (ExcludeSyntax::Regex, rest)
+    } else {
+        (ExcludeSyntax::Glob, line)
+    };
+
+    Some(ExcludeLine {
+        syntax,
+        negated,
+        pattern: pattern.to_string(),
+    })
+}
+
+/// Read an exclude/ignore file and parse it into an ordered list of
+/// [`ExcludeLine`]s. The order is significant: later `!` re-includes only
+/// override patterns that appear earlier in the file.
+pub(crate) fn parse_exclude_file(path: &Path) -> GlobResult<Vec<ExcludeLine>> {
+    let contents = std::fs::read_to_string(path).map_err(|source| GlobError::Io {
+        source,
+        path: path.to_path_buf(),
+    })?;
+
+    Ok(contents.lines().filter_map(parse_exclude_line).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_lines_and_comments_are_skipped() {
+        assert_eq!(parse_exclude_line(""), None);
+        assert_eq!(parse_exclude_line("# a comment"), None);
+    }
+
+    #[test]
+    fn a_bare_line_defaults_to_glob_syntax() {
+        assert_eq!(
+            parse_exclude_line("**/target/**"),
+            Some(ExcludeLine {
+                syntax: ExcludeSyntax::Glob,
+                negated: false,
+                pattern: "**/target/**".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn glob_path_and_re_selectors_are_recognized() {
+        assert_eq!(
+            parse_exclude_line("glob:*.log").unwrap().syntax,
+            ExcludeSyntax::Glob
+        );
+        assert_eq!(
+            parse_exclude_line("path:node_modules").unwrap().syntax,
+            ExcludeSyntax::Path
+        );
+        assert_eq!(
+            parse_exclude_line(r"re:^src/.*\.rs$").unwrap().syntax,
+            ExcludeSyntax::Regex
+        );
+    }
+
+    #[test]
+    fn a_leading_bang_marks_the_line_negated_and_is_stripped_from_the_pattern() {
+        let line = parse_exclude_line("!glob:important.log").unwrap();
+        assert!(line.negated);
+        assert_eq!(line.syntax, ExcludeSyntax::Glob);
+        assert_eq!(line.pattern, "important.log");
+    }
+
+    #[test]
+    fn the_bang_is_consumed_before_the_syntax_selector() {
+        // `!` must come first, same as .gitignore: `path:!foo` would not
+        // be treated as negated.
+        let line = parse_exclude_line("!path:build").unwrap();
+        assert!(line.negated);
+        assert_eq!(line.syntax, ExcludeSyntax::Path);
+        assert_eq!(line.pattern, "build");
+    }
+}